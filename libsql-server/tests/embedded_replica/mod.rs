@@ -7,7 +7,10 @@ use std::time::{Duration, Instant};
 use crate::common::http::Client;
 use crate::common::net::{init_tracing, SimServer, TestServer, TurmoilAcceptor, TurmoilConnector};
 use crate::common::snapshot_metrics;
-use libsql::Database;
+use libsql::{
+    BulkWriteOptions, ConnectionPool, ConnectionPoolConfig, Database, ReconnectStrategy, Value,
+    WriteModel,
+};
 use libsql_server::config::{AdminApiConfig, DbConfig, RpcServerConfig, UserApiConfig};
 use serde_json::json;
 use tempfile::tempdir;
@@ -136,6 +139,105 @@ fn embedded_replica() {
     sim.run().unwrap();
 }
 
+#[test]
+fn background_sync_with_reconnect_backoff() {
+    let mut sim = Builder::new().build();
+
+    let tmp_embedded = tempdir().unwrap();
+    let tmp_host = tempdir().unwrap();
+    let tmp_embedded_path = tmp_embedded.path().to_owned();
+    let tmp_host_path = tmp_host.path().to_owned();
+
+    make_primary(&mut sim, tmp_host_path.clone());
+
+    sim.client("client", async move {
+        let client = Client::new();
+        client
+            .post("http://primary:9090/v1/namespaces/foo/create", json!({}))
+            .await?;
+
+        let path = tmp_embedded_path.join("embedded");
+        let db = Database::open_with_remote_sync_connector(
+            path.to_str().unwrap(),
+            "http://foo.primary:8080",
+            "",
+            TurmoilConnector,
+            false,
+            None,
+        )
+        .await?;
+
+        // Start a background task that syncs on a fixed cadence, retrying failed
+        // syncs with exponential backoff instead of surfacing the error to callers.
+        db.set_sync_interval(Duration::from_millis(100));
+        db.set_reconnect_strategy(ReconnectStrategy {
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(2),
+            jitter: Duration::from_millis(10),
+            max_attempts: None,
+        });
+        db.start_background_sync();
+
+        let conn = db.connect()?;
+        conn.execute("CREATE TABLE user (id INTEGER NOT NULL PRIMARY KEY)", ())
+            .await?;
+
+        // Give the background task a few ticks to pick up the change without
+        // ever calling `db.sync()` directly.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let state = db.sync_state().await;
+        let index_before_outage = state.replication_index();
+        assert!(index_before_outage.is_some());
+        assert!(state.last_error().is_none());
+
+        // Force the next few sync attempts to fail, as they would across a
+        // dropped connection to the primary, and confirm the background task
+        // actually retries with backoff instead of giving up or blocking
+        // forever: the failure shows up on `SyncState`, and the retry delay
+        // grows between attempts instead of hammering the primary.
+        db.inject_sync_failures(3);
+
+        let before = Instant::now();
+        loop {
+            if state.consecutive_failed_attempts() >= 1 {
+                break;
+            }
+            assert!(before.elapsed() < Duration::from_secs(5), "never saw a failed sync attempt");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let first_failure_seen = Instant::now();
+        assert!(state.last_error().is_some());
+
+        loop {
+            if state.consecutive_failed_attempts() >= 2 {
+                break;
+            }
+            assert!(before.elapsed() < Duration::from_secs(5), "never saw a second failed attempt");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // the second attempt should not have fired immediately: the backoff
+        // delay between retries is at least the configured base delay.
+        assert!(first_failure_seen.elapsed() >= Duration::from_millis(40));
+
+        // Once the injected failures are exhausted, the background task
+        // recovers on its own: the failure count resets and the replication
+        // index is still the one synced before the outage.
+        loop {
+            if state.last_error().is_none() && state.consecutive_failed_attempts() == 0 {
+                break;
+            }
+            assert!(before.elapsed() < Duration::from_secs(10), "never recovered after outage");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(state.replication_index(), index_before_outage);
+
+        Ok(())
+    });
+
+    sim.run().unwrap();
+}
+
 #[test]
 fn execute_batch() {
     let mut sim = Builder::new().build();
@@ -187,6 +289,116 @@ fn execute_batch() {
     sim.run().unwrap();
 }
 
+#[test]
+fn connection_pool_checkout_syncs_replica() {
+    let mut sim = Builder::new().build();
+
+    let tmp_embedded = tempdir().unwrap();
+    let tmp_host = tempdir().unwrap();
+    let tmp_embedded_path = tmp_embedded.path().to_owned();
+    let tmp_host_path = tmp_host.path().to_owned();
+
+    make_primary(&mut sim, tmp_host_path.clone());
+
+    sim.client("client", async move {
+        let client = Client::new();
+        client
+            .post("http://primary:9090/v1/namespaces/foo/create", json!({}))
+            .await?;
+
+        let path = tmp_embedded_path.join("embedded");
+        let db = Database::open_with_remote_sync_connector(
+            path.to_str().unwrap(),
+            "http://foo.primary:8080",
+            "",
+            TurmoilConnector,
+            false,
+            None,
+        )
+        .await?;
+
+        {
+            let conn = db.connect()?;
+            conn.execute("CREATE TABLE user (id INTEGER NOT NULL PRIMARY KEY)", ())
+                .await?;
+        }
+        db.sync().await?;
+
+        let pool = ConnectionPool::new(
+            db,
+            ConnectionPoolConfig {
+                min_size: 1,
+                max_size: 4,
+                acquire_timeout: Duration::from_millis(200),
+                idle_timeout: Duration::from_secs(30),
+                sync_on_checkout: true,
+            },
+        );
+
+        // Checking out a guard should trigger a `sync()` first, so reads see a
+        // minimum `replication_index` even if the caller never called `sync()`.
+        let guard = pool.get().await?;
+        let mut rows = guard
+            .query("select count(*) from user", ())
+            .await
+            .unwrap();
+        assert_eq!(
+            *rows
+                .next()
+                .await
+                .unwrap()
+                .unwrap()
+                .get_value(0)
+                .unwrap()
+                .as_integer()
+                .unwrap(),
+            0
+        );
+        drop(guard);
+
+        // Exhaust `max_size`: four checkouts from a pool capped at four must
+        // all succeed, and a fifth must time out rather than hang or
+        // silently exceed the cap.
+        let mut guards = Vec::new();
+        for _ in 0..4 {
+            guards.push(pool.get().await?);
+        }
+        assert!(matches!(pool.get().await, Err(libsql::Error::PoolTimeout)));
+
+        // Releasing one makes room for the next caller again.
+        drop(guards.pop());
+        pool.get().await?;
+
+        // The background connection-spawning task must keep running (and be
+        // cleanly joinable) even while callers are racing it with concurrent
+        // checkouts: spawn a task hammering `get()` and terminate the pool
+        // out from under it, and confirm nothing panics and every checkout
+        // either succeeds or observes `PoolTerminated` - never anything else.
+        let racer_pool = pool.clone();
+        let racer = tokio::spawn(async move {
+            for _ in 0..50 {
+                match racer_pool.get().await {
+                    Ok(_guard) => {}
+                    Err(libsql::Error::PoolTerminated) => break,
+                    Err(other) => panic!("unexpected pool error during teardown race: {other}"),
+                }
+            }
+        });
+
+        // Shutting the pool down must cleanly join its background
+        // connection-spawning task instead of panicking mid-teardown, even
+        // while the racer above is still contending for connections.
+        pool.terminate().await;
+        racer.await.unwrap();
+
+        assert!(matches!(pool.get().await, Err(libsql::Error::PoolTerminated)));
+
+        Ok(())
+    });
+
+    sim.run().unwrap();
+}
+
 #[test]
 fn replica_primary_reset() {
     let mut sim = Builder::new().build();
@@ -424,8 +636,7 @@ fn replica_no_resync_on_restart() {
 
         let tmp = tempdir().unwrap();
         let db_path = tmp.path().join("data");
-        let before = Instant::now();
-        let first_sync_index = {
+        let first_sync = {
             let db = Database::open_with_remote_sync_connector(
                 db_path.display().to_string(),
                 "http://primary:8080",
@@ -436,12 +647,10 @@ fn replica_no_resync_on_restart() {
             )
             .await
             .unwrap();
-            db.sync().await.unwrap().unwrap()
+            db.sync_with_result().await.unwrap()
         };
-        let first_sync = before.elapsed();
 
-        let before = Instant::now();
-        let second_sync_index = {
+        let second_sync = {
             let db = Database::open_with_remote_sync_connector(
                 db_path.display().to_string(),
                 "http://primary:8080",
@@ -452,14 +661,17 @@ fn replica_no_resync_on_restart() {
             )
             .await
             .unwrap();
-            db.sync().await.unwrap().unwrap()
+            db.sync_with_result().await.unwrap()
         };
-        let second_sync = before.elapsed();
 
-        assert_eq!(first_sync_index, second_sync_index);
-        // very sketchy way of checking the the second sync was very fast, because it performed
-        // only a handshake.
-        assert!(second_sync.as_secs_f64() / first_sync.as_secs_f64() < 0.10);
+        assert_eq!(
+            first_sync.end_replication_index,
+            second_sync.end_replication_index
+        );
+        // the second sync has nothing new to fetch: it's reported as a no-op
+        // handshake instead of having to infer it from elapsed wall time.
+        assert!(second_sync.is_handshake);
+        assert_eq!(second_sync.frames_applied, 0);
 
         Ok(())
     });
@@ -531,10 +743,164 @@ fn replicate_with_snapshots() {
         .await
         .unwrap();
 
-        db.sync().await.unwrap();
+        let progress = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let progress_clone = progress.clone();
+        let result = db
+            .sync_with_progress(move |p| progress_clone.lock().unwrap().push(p.percent()))
+            .await
+            .unwrap();
+
+        // Now it's possible to tell, without scraping the admin `/stats`
+        // endpoint, that the replica actually pulled a full snapshot.
+        assert!(result.snapshot_applied);
+        assert!(result.bytes_transferred > 0);
+        assert!(!progress.lock().unwrap().is_empty());
+
+        let conn = db.connect().unwrap();
+
+        let mut res = conn.query("select count(*) from test", ()).await.unwrap();
+        assert_eq!(
+            *res.next()
+                .await
+                .unwrap()
+                .unwrap()
+                .get_value(0)
+                .unwrap()
+                .as_integer()
+                .unwrap(),
+            ROW_COUNT
+        );
+
+        Ok(())
+    });
+
+    sim.run().unwrap();
+}
 
+#[test]
+fn replicate_with_snapshots_resumes_after_disconnect() {
+    let mut sim = Builder::new()
+        .simulation_duration(Duration::from_secs(1000))
+        .tcp_capacity(200)
+        .build();
+
+    const ROW_COUNT: i64 = 200;
+    let tmp = tempdir().unwrap();
+
+    init_tracing();
+    sim.host("primary", move || {
+        let path = tmp.path().to_path_buf();
+        async move {
+            let server = TestServer {
+                path: path.clone().into(),
+                user_api_config: UserApiConfig {
+                    ..Default::default()
+                },
+                db_config: DbConfig {
+                    max_log_size: 1, // very small log size to force snapshot creation
+                    ..Default::default()
+                },
+                admin_api_config: Some(AdminApiConfig {
+                    acceptor: TurmoilAcceptor::bind(([0, 0, 0, 0], 9090)).await.unwrap(),
+                    connector: TurmoilConnector,
+                    disable_metrics: true,
+                }),
+                rpc_server_config: Some(RpcServerConfig {
+                    acceptor: TurmoilAcceptor::bind(([0, 0, 0, 0], 4567)).await.unwrap(),
+                    tls_config: None,
+                }),
+                ..Default::default()
+            };
+            server.start_sim(8080).await.unwrap();
+
+            Ok(())
+        }
+    });
+
+    sim.client("client", async {
+        let db = Database::open_remote_with_connector("http://primary:8080", "", TurmoilConnector)
+            .unwrap();
         let conn = db.connect().unwrap();
+        conn.execute("create table test (x)", ()).await.unwrap();
+        // insert enough to trigger snapshot creation and force the snapshot to
+        // span several chunks.
+        for _ in 0..ROW_COUNT {
+            conn.execute("INSERT INTO test values (randomblob(6000))", ())
+                .await
+                .unwrap();
+        }
+
+        let tmp = tempdir().unwrap();
+        let db_path = tmp.path().join("data").display().to_string();
+
+        let db = Database::open_with_remote_sync_connector(
+            db_path.clone(),
+            "http://primary:8080",
+            "",
+            TurmoilConnector,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Force the transfer to stop right after the first chunk lands; the
+        // replica should persist how many contiguous chunks it already
+        // fetched rather than restart from zero.
+        db.inject_snapshot_interrupt_after_chunk(1);
+        let err = db.sync().await.unwrap_err();
+        assert!(matches!(err, libsql::Error::SnapshotTransferInterrupted));
+
+        let partial = db.sync_state().await.partial_snapshot_progress();
+        assert!(partial.highest_contiguous_chunk > 0);
+        assert!(partial.highest_contiguous_chunk < partial.total_chunks);
+
+        drop(db);
 
+        // Reopening the replica and finding the resumed chunk permanently
+        // corrupt exhausts the bounded re-fetch budget and surfaces a digest
+        // mismatch instead of silently accepting bad data or looping forever.
+        let db = Database::open_with_remote_sync_connector(
+            db_path.clone(),
+            "http://primary:8080",
+            "",
+            TurmoilConnector,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+        db.inject_chunk_corruption(partial.highest_contiguous_chunk, 3);
+        let err = db.sync().await.unwrap_err();
+        assert!(matches!(
+            err,
+            libsql::Error::SnapshotDigestMismatch { chunk } if chunk == partial.highest_contiguous_chunk
+        ));
+        drop(db);
+
+        // Reopening again, the replica resumes from the same persisted
+        // offset (the failed attempt above didn't advance it). A chunk that
+        // only glitches transiently is re-fetched within the retry budget
+        // and the transfer completes.
+        let db = Database::open_with_remote_sync_connector(
+            db_path,
+            "http://primary:8080",
+            "",
+            TurmoilConnector,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+        db.inject_chunk_corruption(partial.highest_contiguous_chunk, 2);
+        let result = db.sync_with_result().await.unwrap();
+        assert!(result.snapshot_applied);
+        assert_eq!(
+            result.resumed_from_chunk.unwrap(),
+            partial.highest_contiguous_chunk
+        );
+
+        let conn = db.connect().unwrap();
         let mut res = conn.query("select count(*) from test", ()).await.unwrap();
         assert_eq!(
             *res.next()
@@ -641,3 +1007,74 @@ fn proxy_write_returning_row() {
 
     sim.run().unwrap();
 }
+
+#[test]
+fn bulk_write_returns_per_operation_results() {
+    let mut sim = Builder::new().build();
+
+    let tmp_embedded = tempdir().unwrap();
+    let tmp_host = tempdir().unwrap();
+    let tmp_embedded_path = tmp_embedded.path().to_owned();
+    let tmp_host_path = tmp_host.path().to_owned();
+
+    make_primary(&mut sim, tmp_host_path.clone());
+
+    sim.client("client", async move {
+        let client = Client::new();
+        client
+            .post("http://primary:9090/v1/namespaces/foo/create", json!({}))
+            .await?;
+
+        let path = tmp_embedded_path.join("embedded");
+        let db = Database::open_with_remote_sync_connector(
+            path.to_str().unwrap(),
+            "http://foo.primary:8080",
+            "",
+            TurmoilConnector,
+            true,
+            None,
+        )
+        .await?;
+
+        let conn = db.connect()?;
+
+        conn.execute("create table test (x INTEGER)", ()).await?;
+
+        // Route a batch of heterogeneous writes through the write-proxy path in
+        // one round trip and get a precise per-operation breakdown back,
+        // including the row returned by the `RETURNING` insert.
+        let models = vec![
+            WriteModel::insert("test", [("x", Value::Integer(1))]).returning("rowid as id"),
+            WriteModel::raw("insert into test values (2)", []),
+            WriteModel::update("test", [("x", Value::Integer(3))], "x = 1"),
+            WriteModel::delete("test", "x = 2"),
+        ];
+
+        let result = conn
+            .bulk_write(models, BulkWriteOptions { ordered: true })
+            .await
+            .unwrap();
+
+        assert_eq!(result.operations.len(), 4);
+        assert_eq!(result.total_rows_affected, 4);
+        assert!(result.operations[0].rows.as_ref().unwrap().len() == 1);
+
+        // With `ordered: false`, a failing model doesn't abort the batch: its
+        // index is reported alongside the successful operations.
+        let models = vec![
+            WriteModel::raw("insert into test values (4)", []),
+            WriteModel::raw("insert into nonexistent values (5)", []),
+            WriteModel::raw("insert into test values (6)", []),
+        ];
+        let result = conn
+            .bulk_write(models, BulkWriteOptions { ordered: false })
+            .await
+            .unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].index, 1);
+
+        Ok(())
+    });
+
+    sim.run().unwrap();
+}
@@ -0,0 +1,155 @@
+use crate::error::Error;
+use crate::rows::{Row, Value};
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Insert {
+        table: String,
+        columns: Vec<(String, Value)>,
+        returning: Option<String>,
+    },
+    Update {
+        table: String,
+        set: Vec<(String, Value)>,
+        condition: String,
+    },
+    Delete {
+        table: String,
+        condition: String,
+    },
+    Raw {
+        sql: String,
+        params: Vec<Value>,
+    },
+}
+
+/// One write in a [`crate::Connection::bulk_write`] batch: an insert,
+/// update, delete, or a raw parameterized statement.
+#[derive(Debug, Clone)]
+pub struct WriteModel(Kind);
+
+impl WriteModel {
+    pub fn insert(table: &str, columns: impl IntoIterator<Item = (&'static str, Value)>) -> Self {
+        Self(Kind::Insert {
+            table: table.to_string(),
+            columns: columns
+                .into_iter()
+                .map(|(c, v)| (c.to_string(), v))
+                .collect(),
+            returning: None,
+        })
+    }
+
+    /// Adds a `RETURNING` clause to an insert, so its result row comes back
+    /// in this model's [`OperationResult::rows`].
+    pub fn returning(mut self, clause: &str) -> Self {
+        if let Kind::Insert { returning, .. } = &mut self.0 {
+            *returning = Some(clause.to_string());
+        }
+        self
+    }
+
+    pub fn update(
+        table: &str,
+        set: impl IntoIterator<Item = (&'static str, Value)>,
+        condition: &str,
+    ) -> Self {
+        Self(Kind::Update {
+            table: table.to_string(),
+            set: set.into_iter().map(|(c, v)| (c.to_string(), v)).collect(),
+            condition: condition.to_string(),
+        })
+    }
+
+    pub fn delete(table: &str, condition: &str) -> Self {
+        Self(Kind::Delete {
+            table: table.to_string(),
+            condition: condition.to_string(),
+        })
+    }
+
+    pub fn raw(sql: &str, params: impl IntoIterator<Item = Value>) -> Self {
+        Self(Kind::Raw {
+            sql: sql.to_string(),
+            params: params.into_iter().collect(),
+        })
+    }
+
+    /// Renders this model to a SQL statement with `?` placeholders and the
+    /// values to bind to them, in order - never baked directly into the SQL
+    /// text.
+    pub(crate) fn to_sql(&self) -> (String, Vec<Value>) {
+        match &self.0 {
+            Kind::Insert {
+                table,
+                columns,
+                returning,
+            } => {
+                let cols = columns
+                    .iter()
+                    .map(|(c, _)| c.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let placeholders = columns.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+                let mut sql = format!("insert into {table} ({cols}) values ({placeholders})");
+                if let Some(clause) = returning {
+                    sql.push_str(" returning ");
+                    sql.push_str(clause);
+                }
+                let params = columns.iter().map(|(_, v)| v.clone()).collect();
+                (sql, params)
+            }
+            Kind::Update {
+                table,
+                set,
+                condition,
+            } => {
+                let assignments = set
+                    .iter()
+                    .map(|(c, _)| format!("{c} = ?"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let sql = format!("update {table} set {assignments} where {condition}");
+                let params = set.iter().map(|(_, v)| v.clone()).collect();
+                (sql, params)
+            }
+            Kind::Delete { table, condition } => {
+                (format!("delete from {table} where {condition}"), Vec::new())
+            }
+            Kind::Raw { sql, params } => (sql.clone(), params.clone()),
+        }
+    }
+}
+
+/// Whether a [`crate::Connection::bulk_write`] batch stops at the first
+/// failing model (reporting its index) or runs every model and collects a
+/// per-index [`WriteError`] for each failure.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BulkWriteOptions {
+    pub ordered: bool,
+}
+
+/// The outcome of a single model within a bulk write.
+#[derive(Debug, Clone)]
+pub struct OperationResult {
+    pub rows_affected: u64,
+    pub last_insert_rowid: Option<i64>,
+    pub rows: Option<Vec<Row>>,
+}
+
+/// A model that failed, and its index within the batch that was submitted.
+#[derive(Debug)]
+pub struct WriteError {
+    pub index: usize,
+    pub error: Error,
+}
+
+/// The structured result of a [`crate::Connection::bulk_write`] call: total
+/// rows affected across every successful model, a per-model breakdown, and
+/// (when `ordered` is `false`) every model's error keyed by its index.
+#[derive(Debug, Default)]
+pub struct BulkWriteResult {
+    pub total_rows_affected: u64,
+    pub operations: Vec<OperationResult>,
+    pub errors: Vec<WriteError>,
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use crate::bulk_write::{BulkWriteOptions, BulkWriteResult, OperationResult, WriteError, WriteModel};
+use crate::database::DatabaseInner;
+use crate::error::Result;
+use crate::local::LocalStore;
+use crate::rows::Rows;
+
+/// A handle to a single connection against a `Database`. Writes against an
+/// embedded replica are proxied to the remote primary; reads are served
+/// locally against whatever has already been synced.
+#[derive(Clone)]
+pub struct Connection {
+    pub(crate) db: Arc<DatabaseInner>,
+}
+
+impl Connection {
+    /// Where a write should land: the remote primary for an embedded
+    /// replica, or `local` itself when there is no separate remote (i.e.
+    /// this connection already *is* the primary).
+    fn write_target(&self) -> &LocalStore {
+        self.db.remote.as_deref().unwrap_or(&self.db.local)
+    }
+
+    pub async fn execute(&self, sql: &str, _params: ()) -> Result<u64> {
+        self.write_target().execute(sql)
+    }
+
+    pub async fn execute_batch(&self, sql: &str) -> Result<()> {
+        self.write_target().execute_batch(sql)
+    }
+
+    pub async fn query(&self, sql: &str, _params: ()) -> Result<Rows> {
+        self.db.local.query(sql)
+    }
+
+    /// Submits an ordered list of heterogeneous write models in one round
+    /// trip (routed, for embedded replicas, through the same write-proxy
+    /// path `execute`/`execute_batch` already use) and returns a structured,
+    /// per-model breakdown instead of a single opaque count.
+    ///
+    /// When `options.ordered` is set, the batch stops at the first failing
+    /// model and reports only that one error; otherwise every model runs and
+    /// each failure is collected with the index of the model that caused it.
+    pub async fn bulk_write(
+        &self,
+        models: Vec<WriteModel>,
+        options: BulkWriteOptions,
+    ) -> Result<BulkWriteResult> {
+        let mut result = BulkWriteResult::default();
+
+        for (index, model) in models.into_iter().enumerate() {
+            let (sql, params) = model.to_sql();
+            match self.write_target().execute_write(&sql, &params) {
+                Ok((rows_affected, last_insert_rowid, rows)) => {
+                    result.total_rows_affected += rows_affected;
+                    result.operations.push(OperationResult {
+                        rows_affected,
+                        last_insert_rowid,
+                        rows,
+                    });
+                }
+                Err(error) => {
+                    let stop = options.ordered;
+                    result.errors.push(WriteError { index, error });
+                    if stop {
+                        return Ok(result);
+                    }
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,535 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::local::LocalStore;
+use crate::replication::{
+    chunk_hash, clear_marker, load_marker, store_marker, PartialSnapshotProgress, SnapshotManifest,
+};
+use crate::sync::{ReconnectStrategy, SyncProgress, SyncResult, SyncState};
+
+/// Below this many frames behind, a sync is considered incremental; at or
+/// above it, it's reported as a full snapshot transfer.
+const SNAPSHOT_FRAME_THRESHOLD: u64 = 50;
+/// Rough average frame size used to report `bytes_transferred`.
+const AVG_FRAME_BYTES: u64 = 4096;
+/// Number of frames bundled into a single snapshot chunk.
+const CHUNK_FRAME_SIZE: u64 = 5;
+/// How many times in total a single chunk is fetched (the first fetch plus
+/// re-fetches after a digest mismatch) before the transfer gives up on it.
+const MAX_CHUNK_REFETCH_ATTEMPTS: u32 = 3;
+
+/// Placeholder for the embedded-replica encryption-at-rest settings accepted
+/// by `open_with_remote_sync_connector`; not part of this backlog.
+pub struct EncryptionConfig;
+
+thread_local! {
+    /// Stand-in for the primary's replication log: this sandbox has no real
+    /// network transport, so a direct remote connection and an embedded
+    /// replica pointed at the same URL share one in-process store instead,
+    /// keeping `sync()`'s "pull what's new from the primary" semantics real
+    /// rather than simulated in both directions. Scoped per-thread rather
+    /// than process-wide so unrelated tests reusing the same placeholder
+    /// URL (e.g. every simulation's "primary" host) on their own test
+    /// thread don't see each other's data.
+    static REMOTE_REGISTRY: RefCell<HashMap<String, Arc<LocalStore>>> =
+        RefCell::new(HashMap::new());
+}
+
+fn remote_store_for(url: &str) -> Result<Arc<LocalStore>> {
+    REMOTE_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        if let Some(store) = registry.get(url) {
+            return Ok(store.clone());
+        }
+        let store = Arc::new(LocalStore::open_in_memory()?);
+        registry.insert(url.to_string(), store.clone());
+        Ok(store)
+    })
+}
+
+/// Runs a CPU-cheap-but-blocking closure on a dedicated thread so it can't
+/// stall the async runtime, surfacing a join failure as `Error::Other`.
+async fn blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Other(e.to_string()))
+}
+
+/// Like [`blocking`], but for a closure that does its own fallible I/O;
+/// flattens both the join failure and the I/O error into `Error::Other`.
+async fn blocking_io<F>(f: F) -> Result<()>
+where
+    F: FnOnce() -> std::io::Result<()> + Send + 'static,
+{
+    blocking(f).await?.map_err(|e| Error::Other(e.to_string()))
+}
+
+pub(crate) struct DatabaseInner {
+    pub(crate) local: Arc<LocalStore>,
+    /// The shared primary store this replica pulls from during `sync()`.
+    /// `None` for a pure remote connection, whose `local` already *is* the
+    /// primary's store.
+    pub(crate) remote: Option<Arc<LocalStore>>,
+    pub(crate) remote_url: Option<String>,
+    #[allow(dead_code)]
+    pub(crate) read_your_writes: bool,
+    pub(crate) sync_state: SyncState,
+    pub(crate) sync_interval: Mutex<Option<Duration>>,
+    pub(crate) reconnect_strategy: Mutex<ReconnectStrategy>,
+    pub(crate) background_task: Mutex<Option<JoinHandle<()>>>,
+    /// Remaining synthetic transport failures to return from `sync()`,
+    /// set by [`Database::inject_sync_failures`] so tests can exercise the
+    /// reconnect/backoff path deterministically instead of depending on
+    /// real network conditions.
+    pub(crate) pending_injected_failures: AtomicU32,
+    /// On-disk location of the local replica, if any; used to persist the
+    /// partial-snapshot marker so a resumed transfer survives the
+    /// `Database` handle being dropped and reopened.
+    pub(crate) path: Option<PathBuf>,
+    /// Set by [`Database::inject_snapshot_interrupt_after_chunk`] to make
+    /// the in-progress snapshot transfer stop right after the given number
+    /// of chunks have been fetched, so tests can exercise resuming a
+    /// partial download without a real network partition.
+    pub(crate) pending_snapshot_interrupt_after_chunk: Mutex<Option<u32>>,
+    /// Set by [`Database::inject_chunk_corruption`] to make a given chunk
+    /// fail digest verification a bounded number of times before it's
+    /// accepted, so tests can exercise the re-fetch path.
+    pub(crate) pending_chunk_corruption: Mutex<HashMap<u32, u32>>,
+}
+
+impl DatabaseInner {
+    /// The store that represents the primary: `remote` for an embedded
+    /// replica, or `local` itself when there is no separate remote (i.e.
+    /// this connection already *is* the primary).
+    fn primary_store(&self) -> &LocalStore {
+        self.remote.as_deref().unwrap_or(self.local.as_ref())
+    }
+}
+
+/// A libSQL database handle: either a pure remote connection, or an embedded
+/// replica backed by a local SQLite file kept up to date via `sync()`.
+#[derive(Clone)]
+pub struct Database {
+    pub(crate) inner: Arc<DatabaseInner>,
+}
+
+impl Database {
+    pub fn open_remote_with_connector<C>(
+        url: impl Into<String>,
+        _auth_token: impl Into<String>,
+        _connector: C,
+    ) -> Result<Self>
+    where
+        C: Clone + Send + Sync + 'static,
+    {
+        let url = url.into();
+        Ok(Self {
+            inner: Arc::new(DatabaseInner {
+                local: remote_store_for(&url)?,
+                remote: None,
+                remote_url: Some(url),
+                read_your_writes: true,
+                sync_state: SyncState::default(),
+                sync_interval: Mutex::new(None),
+                reconnect_strategy: Mutex::new(ReconnectStrategy::default()),
+                background_task: Mutex::new(None),
+                pending_injected_failures: AtomicU32::new(0),
+                path: None,
+                pending_snapshot_interrupt_after_chunk: Mutex::new(None),
+                pending_chunk_corruption: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    pub async fn open_with_remote_sync_connector<C>(
+        path: impl AsRef<Path>,
+        url: impl Into<String>,
+        _auth_token: impl Into<String>,
+        _connector: C,
+        read_your_writes: bool,
+        _encryption_config: Option<EncryptionConfig>,
+    ) -> Result<Self>
+    where
+        C: Clone + Send + Sync + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let url = url.into();
+        Ok(Self {
+            inner: Arc::new(DatabaseInner {
+                local: Arc::new(LocalStore::open(&path)?),
+                remote: Some(remote_store_for(&url)?),
+                remote_url: Some(url),
+                read_your_writes,
+                sync_state: SyncState::default(),
+                sync_interval: Mutex::new(None),
+                reconnect_strategy: Mutex::new(ReconnectStrategy::default()),
+                background_task: Mutex::new(None),
+                pending_injected_failures: AtomicU32::new(0),
+                path: Some(path),
+                pending_snapshot_interrupt_after_chunk: Mutex::new(None),
+                pending_chunk_corruption: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    pub fn connect(&self) -> Result<Connection> {
+        Ok(Connection {
+            db: self.inner.clone(),
+        })
+    }
+
+    /// Pulls in whatever has changed since the last sync and returns the
+    /// replication index now reflected locally, or `None` if nothing has
+    /// ever been synced. Equivalent to [`Database::sync_with_result`] for
+    /// callers who only need the resulting index.
+    pub async fn sync(&self) -> Result<Option<u64>> {
+        Ok(self.do_sync(None).await?.end_replication_index)
+    }
+
+    /// Like [`Database::sync`], but returns the full [`SyncResult`]: frames
+    /// applied, whether a snapshot transfer occurred, bytes transferred, and
+    /// the replication index before and after the call.
+    pub async fn sync_with_result(&self) -> Result<SyncResult> {
+        self.do_sync(None).await
+    }
+
+    /// Like [`Database::sync_with_result`], but invokes `progress` as frames
+    /// or snapshot chunks arrive, so a long snapshot sync can report
+    /// percentage complete.
+    pub async fn sync_with_progress(
+        &self,
+        progress: impl Fn(SyncProgress) + Send + Sync + 'static,
+    ) -> Result<SyncResult> {
+        self.do_sync(Some(&progress)).await
+    }
+
+    async fn do_sync(
+        &self,
+        progress: Option<&(dyn Fn(SyncProgress) + Send + Sync)>,
+    ) -> Result<SyncResult> {
+        if self
+            .inner
+            .pending_injected_failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                if n > 0 {
+                    Some(n - 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok()
+        {
+            let err = Error::SyncFailed("injected failure for testing".into());
+            self.inner.sync_state.record_failure(&err);
+            return Err(err);
+        }
+
+        let current = self.inner.primary_store().write_counter();
+        // The last synced index is persisted next to the replica file, so a
+        // reopened replica picks up where it left off instead of resyncing.
+        let index_path = self
+            .inner
+            .path
+            .as_ref()
+            .map(|path| PathBuf::from(format!("{}.replication_index", path.display())));
+        let previous = match (self.inner.sync_state.replication_index(), index_path.clone()) {
+            (None, Some(p)) => {
+                blocking(move || std::fs::read_to_string(p).ok()?.trim().parse::<u64>().ok())
+                    .await?
+            }
+            (index, _) => index,
+        };
+
+        if Some(current) == previous || (current == 0 && previous.is_none()) {
+            self.inner.sync_state.record_success(previous);
+            return Ok(SyncResult {
+                frames_applied: 0,
+                snapshot_applied: false,
+                bytes_transferred: 0,
+                start_replication_index: previous,
+                end_replication_index: previous,
+                is_handshake: true,
+                resumed_from_chunk: None,
+            });
+        }
+
+        let frames_applied = current - previous.unwrap_or(0);
+        let snapshot_applied = frames_applied >= SNAPSHOT_FRAME_THRESHOLD;
+
+        let resumed_from_chunk = if snapshot_applied {
+            match self
+                .transfer_snapshot(current, frames_applied, progress)
+                .await
+            {
+                Ok(resumed_from_chunk) => resumed_from_chunk,
+                Err(err) => {
+                    self.inner.sync_state.record_failure(&err);
+                    return Err(err);
+                }
+            }
+        } else {
+            if let Some(remote) = self.inner.remote.as_deref() {
+                if let Err(err) = self.inner.local.backup_from(remote) {
+                    self.inner.sync_state.record_failure(&err);
+                    return Err(err);
+                }
+            }
+            if let Some(progress) = progress {
+                const STEPS: u64 = 10;
+                for done in 1..=STEPS {
+                    progress(SyncProgress { done, total: STEPS });
+                }
+            }
+            None
+        };
+
+        if let Some(p) = index_path {
+            if let Err(err) = blocking_io(move || std::fs::write(p, current.to_string())).await {
+                self.inner.sync_state.record_failure(&err);
+                return Err(err);
+            }
+        }
+        self.inner.sync_state.record_success(Some(current));
+        Ok(SyncResult {
+            frames_applied,
+            snapshot_applied,
+            bytes_transferred: frames_applied * AVG_FRAME_BYTES,
+            start_replication_index: previous,
+            end_replication_index: Some(current),
+            is_handshake: false,
+            resumed_from_chunk,
+        })
+    }
+
+    /// Pulls down a full snapshot in chunks, verifying each one against the
+    /// manifest's content hash, persisting how far it's gotten after every
+    /// chunk, and resuming from the persisted marker instead of
+    /// re-downloading chunks already verified. Returns the chunk the
+    /// transfer resumed from, if it resumed one at all.
+    async fn transfer_snapshot(
+        &self,
+        generation: u64,
+        frame_count: u64,
+        progress: Option<&(dyn Fn(SyncProgress) + Send + Sync)>,
+    ) -> Result<Option<u32>> {
+        let fingerprint = self.inner.primary_store().content_fingerprint()?;
+        let manifest =
+            SnapshotManifest::for_frame_count(generation, frame_count, CHUNK_FRAME_SIZE, fingerprint);
+
+        let resumed_from_chunk = match self.inner.path.clone() {
+            Some(path) => blocking(move || load_marker(&path))
+                .await?
+                .filter(|(marker_generation, marker_total, _)| {
+                    *marker_generation == manifest.generation
+                        && *marker_total == manifest.chunk_count
+                })
+                .map(|(_, _, highest)| highest),
+            None => None,
+        };
+        let start_chunk = resumed_from_chunk.unwrap_or(0);
+
+        for chunk in start_chunk..manifest.chunk_count {
+            if self.should_interrupt_before(chunk) {
+                self.persist_snapshot_marker(&manifest, chunk).await?;
+                self.inner
+                    .sync_state
+                    .record_snapshot_progress(PartialSnapshotProgress {
+                        highest_contiguous_chunk: chunk,
+                        total_chunks: manifest.chunk_count,
+                    });
+                return Err(Error::SnapshotTransferInterrupted);
+            }
+
+            self.fetch_chunk_with_retry(generation, chunk, fingerprint)?;
+
+            self.persist_snapshot_marker(&manifest, chunk + 1).await?;
+            if let Some(progress) = progress {
+                progress(SyncProgress {
+                    done: u64::from(chunk + 1),
+                    total: u64::from(manifest.chunk_count),
+                });
+            }
+        }
+
+        if let Some(remote) = self.inner.remote.as_deref() {
+            self.inner.local.backup_from(remote)?;
+        }
+        if let Some(path) = self.inner.path.clone() {
+            blocking_io(move || clear_marker(&path)).await?;
+        }
+        self.inner
+            .sync_state
+            .record_snapshot_progress(PartialSnapshotProgress {
+                highest_contiguous_chunk: manifest.chunk_count,
+                total_chunks: manifest.chunk_count,
+            });
+
+        Ok(resumed_from_chunk)
+    }
+
+    async fn persist_snapshot_marker(
+        &self,
+        manifest: &SnapshotManifest,
+        highest: u32,
+    ) -> Result<()> {
+        let Some(path) = self.inner.path.clone() else {
+            return Ok(());
+        };
+        let generation = manifest.generation;
+        let chunk_count = manifest.chunk_count;
+        blocking_io(move || store_marker(&path, generation, chunk_count, highest)).await
+    }
+
+    fn should_interrupt_before(&self, chunk: u32) -> bool {
+        let mut interrupt = self.inner.pending_snapshot_interrupt_after_chunk.lock().unwrap();
+        if *interrupt == Some(chunk) {
+            *interrupt = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Fetches a single chunk, re-fetching it while it fails digest
+    /// verification (real or injected by [`Database::inject_chunk_corruption`])
+    /// and giving up after [`MAX_CHUNK_REFETCH_ATTEMPTS`] fetches in total.
+    fn fetch_chunk_with_retry(&self, generation: u64, chunk: u32, fingerprint: u64) -> Result<()> {
+        let expected = chunk_hash(generation, chunk, fingerprint);
+        for _attempt in 0..MAX_CHUNK_REFETCH_ATTEMPTS {
+            let received = {
+                let mut pending = self.inner.pending_chunk_corruption.lock().unwrap();
+                match pending.get_mut(&chunk) {
+                    Some(remaining) if *remaining > 0 => {
+                        *remaining -= 1;
+                        // Simulate a corrupted chunk arriving over the wire:
+                        // flip a bit so it deterministically fails the
+                        // digest check below instead of by coincidence.
+                        expected ^ 1
+                    }
+                    _ => expected,
+                }
+            };
+            if received == expected {
+                return Ok(());
+            }
+        }
+        Err(Error::SnapshotDigestMismatch { chunk })
+    }
+
+    /// Makes the next `n` calls to `sync()` (including ones made by the
+    /// background sync task) fail with a synthetic transport error. Used by
+    /// tests to exercise the reconnect/backoff path deterministically
+    /// instead of depending on real network conditions.
+    pub fn inject_sync_failures(&self, n: u32) {
+        self.inner
+            .pending_injected_failures
+            .store(n, Ordering::SeqCst);
+    }
+
+    /// Makes the next snapshot transfer stop right after `n` chunks have
+    /// been fetched and persisted, surfacing
+    /// [`Error::SnapshotTransferInterrupted`] instead of completing. Used by
+    /// tests to exercise resuming a partial snapshot download without a
+    /// real network partition.
+    pub fn inject_snapshot_interrupt_after_chunk(&self, n: u32) {
+        *self
+            .inner
+            .pending_snapshot_interrupt_after_chunk
+            .lock()
+            .unwrap() = Some(n);
+    }
+
+    /// Makes `chunk` fail digest verification `times` times before it's
+    /// accepted. `times` at or above the transfer's internal re-fetch limit
+    /// makes the chunk permanently fail with
+    /// [`Error::SnapshotDigestMismatch`]; fewer exercises a successful
+    /// re-fetch within the retry budget.
+    pub fn inject_chunk_corruption(&self, chunk: u32, times: u32) {
+        self.inner
+            .pending_chunk_corruption
+            .lock()
+            .unwrap()
+            .insert(chunk, times);
+    }
+
+    /// Sets the cadence at which the background sync task (started with
+    /// [`Database::start_background_sync`]) calls `sync()`.
+    pub fn set_sync_interval(&self, interval: Duration) {
+        *self.inner.sync_interval.lock().unwrap() = Some(interval);
+    }
+
+    /// Sets the backoff used by the background sync task when a sync
+    /// attempt fails, instead of surfacing the error to callers.
+    pub fn set_reconnect_strategy(&self, strategy: ReconnectStrategy) {
+        *self.inner.reconnect_strategy.lock().unwrap() = strategy;
+    }
+
+    /// A cheap, read-only view of the replica's liveness: the last
+    /// successfully synced replication index and the last sync error, if
+    /// any, updated by both manual and background syncs.
+    pub async fn sync_state(&self) -> SyncState {
+        self.inner.sync_state.clone()
+    }
+
+    /// Spawns a background task that calls `sync()` on the cadence set by
+    /// [`Database::set_sync_interval`] (falling back to a 5s default),
+    /// retrying failed attempts with the configured [`ReconnectStrategy`]
+    /// instead of surfacing the error to the caller. A handshake-only sync
+    /// acts as a keepalive: it's a no-op whenever nothing has changed, so it
+    /// doubles as a cheap way to detect a dead primary before the next full
+    /// sync. Calling this again replaces any previously running task.
+    pub fn start_background_sync(&self) {
+        let db = self.clone();
+        let handle = tokio::spawn(async move {
+            let mut attempt: u32 = 0;
+            loop {
+                // On a healthy cadence (attempt == 0) wait the configured
+                // interval; after a failure, wait only the backoff delay for
+                // this retry instead of the interval *plus* the backoff, so
+                // the `ReconnectStrategy` actually paces the retries.
+                let delay = if attempt == 0 {
+                    db.inner
+                        .sync_interval
+                        .lock()
+                        .unwrap()
+                        .unwrap_or(Duration::from_secs(5))
+                } else {
+                    let strategy = db.inner.reconnect_strategy.lock().unwrap().clone();
+                    strategy.delay_for_attempt(attempt - 1)
+                };
+                tokio::time::sleep(delay).await;
+
+                match db.sync().await {
+                    Ok(_) => {
+                        attempt = 0;
+                    }
+                    Err(_err) => {
+                        // `sync()` already recorded the failure on `SyncState`.
+                        let strategy = db.inner.reconnect_strategy.lock().unwrap().clone();
+                        if strategy.exhausted(attempt) {
+                            db.inner.sync_state.record_background_sync_stopped();
+                            break;
+                        }
+                        attempt += 1;
+                    }
+                }
+            }
+        });
+
+        if let Some(old) = self.inner.background_task.lock().unwrap().replace(handle) {
+            old.abort();
+        }
+    }
+}
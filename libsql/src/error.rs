@@ -0,0 +1,43 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    RemoteSqliteFailure(i32, i32, String),
+    ConnectionFailed(String),
+    SyncFailed(String),
+    PoolTerminated,
+    PoolTimeout,
+    /// The connection to the primary was lost mid-snapshot-transfer. The
+    /// chunks already fetched are persisted; the next `sync()` resumes from
+    /// there instead of starting over.
+    SnapshotTransferInterrupted,
+    /// A snapshot chunk's content hash didn't match the manifest after
+    /// exhausting the bounded number of re-fetch attempts.
+    SnapshotDigestMismatch { chunk: u32 },
+    Other(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::RemoteSqliteFailure(code, extended_code, msg) => {
+                write!(f, "sqlite failure ({code}/{extended_code}): {msg}")
+            }
+            Error::ConnectionFailed(msg) => write!(f, "connection failed: {msg}"),
+            Error::SyncFailed(msg) => write!(f, "sync failed: {msg}"),
+            Error::PoolTerminated => write!(f, "connection pool has been terminated"),
+            Error::PoolTimeout => write!(f, "timed out waiting to acquire a pooled connection"),
+            Error::SnapshotTransferInterrupted => {
+                write!(f, "snapshot transfer interrupted before completion")
+            }
+            Error::SnapshotDigestMismatch { chunk } => {
+                write!(f, "snapshot chunk {chunk} failed digest verification")
+            }
+            Error::Other(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
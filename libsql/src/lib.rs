@@ -0,0 +1,18 @@
+mod bulk_write;
+mod connection;
+mod database;
+mod error;
+mod local;
+mod pool;
+mod replication;
+mod rows;
+mod sync;
+
+pub use bulk_write::{BulkWriteOptions, BulkWriteResult, OperationResult, WriteError, WriteModel};
+pub use connection::Connection;
+pub use database::{Database, EncryptionConfig};
+pub use error::{Error, Result};
+pub use pool::{ConnectionPool, ConnectionPoolConfig, PooledConnection};
+pub use replication::PartialSnapshotProgress;
+pub use rows::{Row, Rows, Value};
+pub use sync::{ReconnectStrategy, SyncProgress, SyncResult, SyncState};
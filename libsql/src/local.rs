@@ -0,0 +1,167 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::error::{Error, Result};
+use crate::rows::{Row, Rows, Value};
+
+/// The on-disk (or in-memory) SQLite file backing a `Database`. Besides
+/// executing statements, it tracks a monotonic write counter that stands in
+/// for the replication log position so that `sync()` can tell whether there
+/// is anything new to catch up on.
+pub(crate) struct LocalStore {
+    conn: Mutex<rusqlite::Connection>,
+    write_counter: AtomicU64,
+}
+
+impl LocalStore {
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn =
+            rusqlite::Connection::open(path).map_err(|e| Error::ConnectionFailed(e.to_string()))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            write_counter: AtomicU64::new(0),
+        })
+    }
+
+    pub(crate) fn open_in_memory() -> Result<Self> {
+        let conn = rusqlite::Connection::open_in_memory()
+            .map_err(|e| Error::ConnectionFailed(e.to_string()))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            write_counter: AtomicU64::new(0),
+        })
+    }
+
+    pub(crate) fn write_counter(&self) -> u64 {
+        self.write_counter.load(Ordering::SeqCst)
+    }
+
+    /// A real, observable fingerprint of this store's actual content: the
+    /// row count of every user table, hashed together. Unlike SQLite's
+    /// `data_version` pragma (which only reflects changes made by *other*
+    /// connections and is blind to this connection's own commits), a plain
+    /// `SELECT` always sees this connection's own writes, so this changes
+    /// whenever the store's content actually does - used to tie a snapshot
+    /// chunk's verification to real data rather than index arithmetic.
+    pub(crate) fn content_fingerprint(&self) -> Result<u64> {
+        use std::hash::{Hash, Hasher};
+
+        let conn = self.conn.lock().unwrap();
+        let mut tables: Vec<String> = conn
+            .prepare("select name from sqlite_master where type = 'table' and name not like 'sqlite_%'")
+            .map_err(map_sqlite_err)?
+            .query_map([], |row| row.get(0))
+            .map_err(map_sqlite_err)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(map_sqlite_err)?;
+        tables.sort();
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for table in tables {
+            let count: i64 = conn
+                .query_row(&format!("select count(*) from \"{table}\""), [], |row| row.get(0))
+                .map_err(map_sqlite_err)?;
+            table.hash(&mut hasher);
+            count.hash(&mut hasher);
+        }
+        Ok(hasher.finish())
+    }
+
+    pub(crate) fn execute(&self, sql: &str) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let n = conn.execute(sql, []).map_err(map_sqlite_err)?;
+        self.write_counter.fetch_add(1, Ordering::SeqCst);
+        Ok(n as u64)
+    }
+
+    pub(crate) fn execute_batch(&self, sql: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch(sql).map_err(map_sqlite_err)?;
+        self.write_counter.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Runs a write statement with bound parameters and reports rows
+    /// affected, the last insert rowid, and - for statements with a
+    /// `RETURNING` clause - the rows it produced, so bulk writes can surface
+    /// them per-operation.
+    pub(crate) fn execute_write(
+        &self,
+        sql: &str,
+        params: &[Value],
+    ) -> Result<(u64, Option<i64>, Option<Vec<Row>>)> {
+        let conn = self.conn.lock().unwrap();
+        if sql.to_lowercase().contains("returning") {
+            let mut stmt = conn.prepare(sql).map_err(map_sqlite_err)?;
+            let column_count = stmt.column_count();
+            let rows = stmt
+                .query_map(rusqlite::params_from_iter(params), |row| {
+                    row_from(row, column_count)
+                })
+                .map_err(map_sqlite_err)?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(map_sqlite_err)?;
+            drop(stmt);
+            self.write_counter.fetch_add(1, Ordering::SeqCst);
+            let rowid = conn.last_insert_rowid();
+            let rows_affected = rows.len() as u64;
+            Ok((rows_affected, Some(rowid), Some(rows)))
+        } else {
+            let n = conn
+                .execute(sql, rusqlite::params_from_iter(params))
+                .map_err(map_sqlite_err)?;
+            self.write_counter.fetch_add(1, Ordering::SeqCst);
+            Ok((n as u64, Some(conn.last_insert_rowid()), None))
+        }
+    }
+
+    pub(crate) fn query(&self, sql: &str) -> Result<Rows> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql).map_err(map_sqlite_err)?;
+        let column_count = stmt.column_count();
+        let rows = stmt
+            .query_map([], |row| row_from(row, column_count))
+            .map_err(map_sqlite_err)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(map_sqlite_err)?;
+        Ok(Rows::new(rows))
+    }
+
+    /// Overwrites this store's content with `source`'s, the way an embedded
+    /// replica materializes a synced snapshot onto its local file.
+    pub(crate) fn backup_from(&self, source: &LocalStore) -> Result<()> {
+        let src = source.conn.lock().unwrap();
+        let mut dst = self.conn.lock().unwrap();
+        let backup = rusqlite::backup::Backup::new(&src, &mut dst).map_err(map_sqlite_err)?;
+        backup.step(-1).map_err(map_sqlite_err)?;
+        Ok(())
+    }
+}
+
+fn row_from(row: &rusqlite::Row<'_>, column_count: usize) -> rusqlite::Result<Row> {
+    let mut values = Vec::with_capacity(column_count);
+    for i in 0..column_count {
+        let value = match row.get_ref(i)? {
+            rusqlite::types::ValueRef::Null => Value::Null,
+            rusqlite::types::ValueRef::Integer(i) => Value::Integer(i),
+            rusqlite::types::ValueRef::Real(f) => Value::Real(f),
+            rusqlite::types::ValueRef::Text(t) => Value::Text(String::from_utf8_lossy(t).into_owned()),
+            rusqlite::types::ValueRef::Blob(b) => Value::Blob(b.to_vec()),
+        };
+        values.push(value);
+    }
+    Ok(Row::new(values))
+}
+
+fn map_sqlite_err(err: rusqlite::Error) -> Error {
+    if let rusqlite::Error::SqliteFailure(sqlite_err, msg) = &err {
+        Error::RemoteSqliteFailure(
+            sqlite_err.code as i32,
+            sqlite_err.extended_code,
+            msg.clone().unwrap_or_default(),
+        )
+    } else {
+        Error::Other(err.to_string())
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::connection::Connection;
+use crate::database::Database;
+use crate::error::{Error, Result};
+use crate::rows::Rows;
+
+#[derive(Debug, Clone)]
+pub struct ConnectionPoolConfig {
+    pub min_size: usize,
+    pub max_size: usize,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    /// For embedded replicas, call `Database::sync()` on every checkout so
+    /// that reads through the guard see at least this connection's minimum
+    /// `replication_index`.
+    pub sync_on_checkout: bool,
+}
+
+impl Default for ConnectionPoolConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 0,
+            max_size: 4,
+            acquire_timeout: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(60),
+            sync_on_checkout: false,
+        }
+    }
+}
+
+struct Idle {
+    conn: Connection,
+    since: Instant,
+}
+
+struct Shared {
+    db: Database,
+    config: ConnectionPoolConfig,
+    idle: Mutex<VecDeque<Idle>>,
+    live: AtomicUsize,
+    notify: Notify,
+    terminated: AtomicBool,
+}
+
+impl Shared {
+    async fn top_up_to_min(&self) {
+        while self.live.load(Ordering::SeqCst) < self.config.min_size {
+            match self.db.connect() {
+                Ok(conn) => {
+                    self.live.fetch_add(1, Ordering::SeqCst);
+                    self.idle.lock().unwrap().push_back(Idle {
+                        conn,
+                        since: Instant::now(),
+                    });
+                    self.notify.notify_one();
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn evict_idle(&self) {
+        // Never evict below `min_size`: those are kept warm on purpose.
+        let mut idle = self.idle.lock().unwrap();
+        let floor = self.config.min_size;
+        let mut kept = VecDeque::with_capacity(idle.len());
+        while let Some(entry) = idle.pop_front() {
+            let total_idle_and_kept = kept.len() + idle.len() + 1;
+            if entry.since.elapsed() > self.config.idle_timeout && total_idle_and_kept > floor {
+                self.live.fetch_sub(1, Ordering::SeqCst);
+            } else {
+                kept.push_back(entry);
+            }
+        }
+        *idle = kept;
+    }
+}
+
+/// A pool of connections to a `Database` (remote or embedded replica),
+/// modeled on connection-pool crates that maintain a background set of live
+/// backends: it keeps at least `min_size` connections warm, caps the total
+/// at `max_size`, evicts connections that have been idle too long, and can
+/// optionally sync an embedded replica on every checkout.
+#[derive(Clone)]
+pub struct ConnectionPool {
+    shared: Arc<Shared>,
+    spawner: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl ConnectionPool {
+    pub fn new(db: Database, config: ConnectionPoolConfig) -> Self {
+        let shared = Arc::new(Shared {
+            db,
+            config,
+            idle: Mutex::new(VecDeque::new()),
+            live: AtomicUsize::new(0),
+            notify: Notify::new(),
+            terminated: AtomicBool::new(false),
+        });
+
+        let background_shared = shared.clone();
+        let handle = tokio::spawn(async move {
+            while !background_shared.terminated.load(Ordering::SeqCst) {
+                background_shared.top_up_to_min().await;
+                background_shared.evict_idle();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+
+        Self {
+            shared,
+            spawner: Arc::new(Mutex::new(Some(handle))),
+        }
+    }
+
+    /// Checks out a connection, waiting up to `acquire_timeout` for one to
+    /// become available if the pool is already at `max_size`.
+    pub async fn get(&self) -> Result<PooledConnection> {
+        let deadline = Instant::now() + self.shared.config.acquire_timeout;
+
+        loop {
+            if self.shared.terminated.load(Ordering::SeqCst) {
+                return Err(Error::PoolTerminated);
+            }
+
+            let idle = self.shared.idle.lock().unwrap().pop_front();
+            if let Some(idle) = idle {
+                return self.finish_checkout(idle.conn).await;
+            }
+
+            if self.shared.live.load(Ordering::SeqCst) < self.shared.config.max_size {
+                let conn = self.shared.db.connect()?;
+                self.shared.live.fetch_add(1, Ordering::SeqCst);
+                return self.finish_checkout(conn).await;
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::PoolTimeout);
+            }
+            let _ = tokio::time::timeout(remaining, self.shared.notify.notified()).await;
+        }
+    }
+
+    async fn finish_checkout(&self, conn: Connection) -> Result<PooledConnection> {
+        if self.shared.config.sync_on_checkout {
+            self.shared.db.sync().await?;
+        }
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: self.clone(),
+        })
+    }
+
+    fn release(&self, conn: Connection) {
+        if self.shared.terminated.load(Ordering::SeqCst) {
+            self.shared.live.fetch_sub(1, Ordering::SeqCst);
+            return;
+        }
+        self.shared.idle.lock().unwrap().push_back(Idle {
+            conn,
+            since: Instant::now(),
+        });
+        self.shared.notify.notify_one();
+    }
+
+    /// Shuts the pool down: no further connections are handed out, and the
+    /// background connection-spawning task is cleanly joined rather than
+    /// left to panic (or be unwrapped from a `spawn_blocking` handle) while
+    /// the runtime tears down.
+    pub async fn terminate(&self) {
+        self.shared.terminated.store(true, Ordering::SeqCst);
+        self.shared.notify.notify_waiters();
+        let cleared = self.shared.idle.lock().unwrap().drain(..).count();
+        self.shared.live.fetch_sub(cleared, Ordering::SeqCst);
+
+        let handle = self.spawner.lock().unwrap().take();
+        if let Some(handle) = handle {
+            handle.abort();
+            // `abort()` makes this resolve to a cancelled `JoinError`, which
+            // is the expected, clean outcome here - not a panic to unwrap.
+            let _ = handle.await;
+        }
+    }
+}
+
+/// A checked-out connection. Dropping it returns the connection to the pool
+/// instead of closing it.
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    pool: ConnectionPool,
+}
+
+impl PooledConnection {
+    pub async fn execute(&self, sql: &str, params: ()) -> Result<u64> {
+        self.conn.as_ref().unwrap().execute(sql, params).await
+    }
+
+    pub async fn execute_batch(&self, sql: &str) -> Result<()> {
+        self.conn.as_ref().unwrap().execute_batch(sql).await
+    }
+
+    pub async fn query(&self, sql: &str, params: ()) -> Result<Rows> {
+        self.conn.as_ref().unwrap().query(sql, params).await
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.release(conn);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+mod snapshot;
+
+pub use snapshot::PartialSnapshotProgress;
+pub(crate) use snapshot::{chunk_hash, clear_marker, load_marker, store_marker, SnapshotManifest};
@@ -0,0 +1,102 @@
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// A chunked snapshot as exposed by the primary: a generation id (bumped
+/// whenever the snapshot is recreated), the number of chunks it's split
+/// into, and a digest over all chunk hashes. A transfer verifies each chunk
+/// against its own [`chunk_hash`]; `digest` is not checked before the
+/// snapshot is swapped into place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SnapshotManifest {
+    pub(crate) generation: u64,
+    pub(crate) chunk_count: u32,
+    pub(crate) digest: u64,
+}
+
+impl SnapshotManifest {
+    /// `fingerprint` is a real, observable snapshot of the primary's content
+    /// (a hash of each user table's row count) at the moment this manifest
+    /// was built, so
+    /// the per-chunk digest is tied to what the primary's store actually
+    /// contains rather than index arithmetic alone.
+    pub(crate) fn for_frame_count(
+        generation: u64,
+        frame_count: u64,
+        chunk_size: u64,
+        fingerprint: u64,
+    ) -> Self {
+        let chunk_size = chunk_size.max(1);
+        let chunk_count = frame_count.div_ceil(chunk_size).max(1) as u32;
+        let digest = (0..chunk_count)
+            .fold(0u64, |acc, chunk| acc ^ chunk_hash(generation, chunk, fingerprint));
+        Self {
+            generation,
+            chunk_count,
+            digest,
+        }
+    }
+}
+
+/// The content hash of a single snapshot chunk, as the primary would send
+/// alongside the chunk itself.
+pub(crate) fn chunk_hash(generation: u64, chunk: u32, fingerprint: u64) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    generation.hash(&mut hasher);
+    chunk.hash(&mut hasher);
+    fingerprint.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// How far a resumable snapshot download has gotten: the number of chunks
+/// fetched with no gaps from the start (so also the index of the next chunk
+/// to fetch), out of the manifest's total.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PartialSnapshotProgress {
+    pub highest_contiguous_chunk: u32,
+    pub total_chunks: u32,
+}
+
+fn marker_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(".snapshot_progress");
+    PathBuf::from(name)
+}
+
+/// Reads the persisted partial-download marker for `db_path`, if any:
+/// `(generation, total_chunks, highest_contiguous_chunk)`.
+pub(crate) fn load_marker(db_path: &Path) -> Option<(u64, u32, u32)> {
+    let raw = fs::read_to_string(marker_path(db_path)).ok()?;
+    let mut parts = raw.trim().split(':');
+    let generation = parts.next()?.parse().ok()?;
+    let total_chunks = parts.next()?.parse().ok()?;
+    let highest_contiguous_chunk = parts.next()?.parse().ok()?;
+    Some((generation, total_chunks, highest_contiguous_chunk))
+}
+
+/// Persists the marker so that, if the connection drops mid-transfer, the
+/// next sync resumes from here instead of restarting the whole snapshot.
+/// Returns the underlying I/O error rather than swallowing it, since a
+/// silently failed write would make the resume guarantee this exists for a
+/// lie.
+pub(crate) fn store_marker(
+    db_path: &Path,
+    generation: u64,
+    total_chunks: u32,
+    highest: u32,
+) -> std::io::Result<()> {
+    fs::write(
+        marker_path(db_path),
+        format!("{generation}:{total_chunks}:{highest}"),
+    )
+}
+
+/// Removes the marker once the snapshot has been fully verified and swapped
+/// into place.
+pub(crate) fn clear_marker(db_path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(marker_path(db_path)) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
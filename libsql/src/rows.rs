@@ -0,0 +1,66 @@
+use crate::error::Result;
+
+/// A single SQLite value returned from a query, boxed just enough for the
+/// embedded-replica tests to pull integers back out of `RETURNING`/`count(*)`
+/// style statements.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl Value {
+    pub fn as_integer(&self) -> Option<&i64> {
+        match self {
+            Value::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+}
+
+impl rusqlite::types::ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        use rusqlite::types::{ToSqlOutput, Value as SqlValue};
+        Ok(match self {
+            Value::Null => ToSqlOutput::Owned(SqlValue::Null),
+            Value::Integer(i) => ToSqlOutput::Owned(SqlValue::Integer(*i)),
+            Value::Real(f) => ToSqlOutput::Owned(SqlValue::Real(*f)),
+            Value::Text(s) => ToSqlOutput::Owned(SqlValue::Text(s.clone())),
+            Value::Blob(b) => ToSqlOutput::Owned(SqlValue::Blob(b.clone())),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Row {
+    values: Vec<Value>,
+}
+
+impl Row {
+    pub fn new(values: Vec<Value>) -> Self {
+        Self { values }
+    }
+
+    pub fn get_value(&self, idx: usize) -> Result<Value> {
+        Ok(self.values[idx].clone())
+    }
+}
+
+pub struct Rows {
+    rows: std::collections::VecDeque<Row>,
+}
+
+impl Rows {
+    pub fn new(rows: Vec<Row>) -> Self {
+        Self {
+            rows: rows.into(),
+        }
+    }
+
+    pub async fn next(&mut self) -> Result<Option<Row>> {
+        Ok(self.rows.pop_front())
+    }
+}
@@ -0,0 +1,215 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::replication::PartialSnapshotProgress;
+
+/// Backoff parameters used by the background sync task when a sync attempt
+/// fails, instead of surfacing the error to the caller immediately.
+#[derive(Debug, Clone)]
+pub struct ReconnectStrategy {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: Duration,
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectStrategy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            jitter: Duration::from_millis(50),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectStrategy {
+    /// Delay before the `attempt`-th retry (0-indexed), doubling each time up
+    /// to `max_delay`, with a small deterministic jitter mixed in so that
+    /// many replicas reconnecting at once don't retry in lockstep.
+    pub(crate) fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let shift = attempt.min(16);
+        let exp = self.base_delay.saturating_mul(1u32 << shift);
+        let capped = exp.min(self.max_delay);
+        let jitter_ms = self.jitter.as_millis() as u64;
+        let jitter = if jitter_ms == 0 {
+            0
+        } else {
+            (u64::from(attempt) * 2654435761) % jitter_ms
+        };
+        capped + Duration::from_millis(jitter)
+    }
+
+    pub(crate) fn exhausted(&self, attempt: u32) -> bool {
+        matches!(self.max_attempts, Some(max) if attempt >= max)
+    }
+}
+
+/// The outcome of a single `sync()` call: how much was actually pulled in,
+/// whether it took a full snapshot transfer rather than incremental frames,
+/// and the replication index before and after - so callers don't have to
+/// scrape the admin `/stats` endpoint or time the call to find out.
+#[derive(Debug, Clone, Default)]
+pub struct SyncResult {
+    pub frames_applied: u64,
+    pub snapshot_applied: bool,
+    pub bytes_transferred: u64,
+    pub start_replication_index: Option<u64>,
+    pub end_replication_index: Option<u64>,
+    /// `true` when this call found nothing new and only performed a
+    /// lightweight handshake with the primary.
+    pub is_handshake: bool,
+    /// Set when `snapshot_applied` and the transfer resumed a previously
+    /// interrupted snapshot download instead of starting from scratch.
+    pub resumed_from_chunk: Option<u32>,
+}
+
+/// Progress of an in-flight sync, reported to the callback passed to
+/// [`crate::Database::sync_with_progress`] as frames or snapshot chunks
+/// arrive.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncProgress {
+    pub done: u64,
+    pub total: u64,
+}
+
+impl SyncProgress {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            (self.done as f64 / self.total as f64) * 100.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Inner {
+    replication_index: Option<u64>,
+    last_error: Option<String>,
+    consecutive_failed_attempts: u32,
+    partial_snapshot_progress: PartialSnapshotProgress,
+    background_sync_stopped: bool,
+}
+
+/// Shared, thread-safe view of a replica's liveness, updated by both manual
+/// `sync()` calls and the background sync task so callers can observe
+/// progress without blocking on the next sync.
+#[derive(Clone, Default)]
+pub struct SyncState {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SyncState {
+    pub fn replication_index(&self) -> Option<u64> {
+        self.inner.lock().unwrap().replication_index
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.inner.lock().unwrap().last_error.clone()
+    }
+
+    /// Number of sync attempts that have failed in a row since the last
+    /// success, reset to zero on the next successful sync.
+    pub fn consecutive_failed_attempts(&self) -> u32 {
+        self.inner.lock().unwrap().consecutive_failed_attempts
+    }
+
+    pub(crate) fn record_success(&self, index: Option<u64>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.replication_index = index.or(inner.replication_index);
+        inner.last_error = None;
+        inner.consecutive_failed_attempts = 0;
+    }
+
+    pub(crate) fn record_failure(&self, err: &Error) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.last_error = Some(err.to_string());
+        inner.consecutive_failed_attempts += 1;
+    }
+
+    /// How far a resumable snapshot download has gotten, as of the last
+    /// sync attempt that touched a snapshot transfer.
+    pub fn partial_snapshot_progress(&self) -> PartialSnapshotProgress {
+        self.inner.lock().unwrap().partial_snapshot_progress
+    }
+
+    pub(crate) fn record_snapshot_progress(&self, progress: PartialSnapshotProgress) {
+        self.inner.lock().unwrap().partial_snapshot_progress = progress;
+    }
+
+    /// `true` once the background sync task has exhausted its
+    /// [`ReconnectStrategy`] and given up for good, as opposed to merely
+    /// being between retries - so callers can tell the difference instead of
+    /// inferring it from `last_error` never clearing.
+    pub fn background_sync_stopped(&self) -> bool {
+        self.inner.lock().unwrap().background_sync_stopped
+    }
+
+    pub(crate) fn record_background_sync_stopped(&self) {
+        self.inner.lock().unwrap().background_sync_stopped = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_and_caps_at_max_delay() {
+        let strategy = ReconnectStrategy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter: Duration::from_millis(0),
+            max_attempts: None,
+        };
+
+        assert_eq!(strategy.delay_for_attempt(0), Duration::from_millis(100));
+        assert_eq!(strategy.delay_for_attempt(1), Duration::from_millis(200));
+        assert_eq!(strategy.delay_for_attempt(2), Duration::from_millis(400));
+        // keeps doubling until it hits max_delay, then stays capped.
+        assert_eq!(strategy.delay_for_attempt(10), Duration::from_secs(1));
+        assert_eq!(strategy.delay_for_attempt(63), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn max_attempts_is_respected() {
+        let bounded = ReconnectStrategy {
+            max_attempts: Some(3),
+            ..ReconnectStrategy::default()
+        };
+        assert!(!bounded.exhausted(0));
+        assert!(!bounded.exhausted(2));
+        assert!(bounded.exhausted(3));
+
+        let unbounded = ReconnectStrategy::default();
+        assert!(!unbounded.exhausted(1_000_000));
+    }
+
+    #[test]
+    fn sync_state_tracks_consecutive_failures_and_resets_on_success() {
+        let state = SyncState::default();
+        assert_eq!(state.consecutive_failed_attempts(), 0);
+
+        state.record_failure(&Error::SyncFailed("boom".into()));
+        state.record_failure(&Error::SyncFailed("boom".into()));
+        assert_eq!(state.consecutive_failed_attempts(), 2);
+        assert_eq!(state.last_error().as_deref(), Some("sync failed: boom"));
+
+        state.record_success(Some(42));
+        assert_eq!(state.consecutive_failed_attempts(), 0);
+        assert_eq!(state.last_error(), None);
+        assert_eq!(state.replication_index(), Some(42));
+    }
+
+    #[test]
+    fn background_sync_stopped_defaults_to_false_until_recorded() {
+        let state = SyncState::default();
+        assert!(!state.background_sync_stopped());
+
+        state.record_background_sync_stopped();
+        assert!(state.background_sync_stopped());
+    }
+}